@@ -0,0 +1,430 @@
+//! Governance proposal tallying and execution, run at block finalization.
+//!
+//! Unlike `validate_tx`, the functions in this module never run as a VP
+//! (native or WASM) — they're invoked once per block, directly by the
+//! ledger's finalize-block hook, which only ever has a concrete
+//! `Storage`. They intentionally read straight from `Storage`/`pos`
+//! rather than through `StorageReader`, which exists solely to let VP
+//! validation logic run unmodified under either environment.
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use super::{storage as gov_storage, ProposalType};
+use crate::ledger::pos;
+use crate::ledger::storage::{self as ledger_storage, Storage, StorageHasher};
+use crate::types::address::{xan as m1t, Address};
+use crate::types::governance::ProposalVote;
+use crate::types::storage::{DbKeySeg, Epoch, Key};
+use crate::types::token::Amount;
+
+/// The outcome of tallying the votes cast on a proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum TallyOutcome {
+    /// The proposal met the passing threshold.
+    Passed,
+    /// The proposal did not meet the passing threshold.
+    Rejected,
+}
+
+/// The (possibly partial) tally of a proposal's votes.
+#[derive(Debug, Clone, Copy)]
+pub struct TallyResult {
+    /// Total voting power that voted Yay
+    pub total_yay_power: Amount,
+    /// Total voting power that voted Nay
+    pub total_nay_power: Amount,
+    /// The outcome of the tally computed so far
+    pub result: TallyOutcome,
+}
+
+/// Resolve every proposal whose voting period ended in `current_epoch`:
+/// tally the votes, move the escrowed funds, and execute the wasm code
+/// of proposals that reached their grace epoch.
+pub fn finalize_governance_proposals<D, H>(
+    storage: &mut Storage<D, H>,
+    current_epoch: Epoch,
+) -> Vec<TallyResult>
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let mut results = Vec::new();
+
+    // `current_epoch` stays the same across every block of that epoch,
+    // while `proposals_ending_at`/`proposals_at_grace_epoch` only filter
+    // on the epoch matching, so without these guards every block of the
+    // epoch would re-tally, re-settle and re-execute the same proposals.
+    for proposal_id in proposals_ending_at(storage, current_epoch) {
+        if has_tally_result(storage, proposal_id) {
+            continue;
+        }
+        let tally = tally_proposal(storage, proposal_id);
+        // Persist the outcome so `execute_proposal` can check it later,
+        // once the proposal reaches its (possibly much later) grace
+        // epoch, and so later blocks of this epoch see it as already
+        // settled.
+        write_tally_result(storage, proposal_id, tally.result);
+        settle_funds(storage, proposal_id, tally.result);
+        results.push(tally);
+    }
+
+    for proposal_id in proposals_at_grace_epoch(storage, current_epoch) {
+        if has_been_executed(storage, proposal_id) {
+            continue;
+        }
+        execute_proposal(storage, proposal_id);
+        mark_executed(storage, proposal_id);
+    }
+
+    results
+}
+
+/// Compute the current tally of a single proposal from the votes cast so
+/// far, without mutating any storage. Useful for clients that want to
+/// query the state of an on-going vote. Fails closed: a proposal whose
+/// `min_proposal_threshold` parameter cannot be read is `Rejected`,
+/// never treated as if the threshold were zero.
+pub fn tally_proposal<D, H>(
+    storage: &Storage<D, H>,
+    proposal_id: u64,
+) -> TallyResult
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let start_epoch_key = gov_storage::get_voting_start_epoch_key(proposal_id);
+    let start_epoch = Epoch(read(storage, &start_epoch_key).unwrap_or_default());
+
+    let mut total_yay_power = Amount::default();
+    let mut total_nay_power = Amount::default();
+    for (voter, vote) in read_votes(storage, proposal_id) {
+        let power = pos::voting_power_at(storage, &voter, start_epoch);
+        match vote {
+            ProposalVote::Yay => total_yay_power += power,
+            ProposalVote::Nay => total_nay_power += power,
+        }
+    }
+    let total_active_stake = pos::total_active_stake(storage, start_epoch);
+
+    let min_proposal_threshold_key =
+        gov_storage::get_min_proposal_threshold_key();
+    let min_proposal_threshold: Option<Amount> =
+        read(storage, &min_proposal_threshold_key);
+
+    // Fail closed: a missing threshold parameter must not let every
+    // proposal pass with zero Yay power.
+    let result = match min_proposal_threshold {
+        Some(min_proposal_threshold) => {
+            let required_power = required_yay_power(
+                total_active_stake,
+                min_proposal_threshold,
+            );
+            if total_yay_power >= required_power {
+                TallyOutcome::Passed
+            } else {
+                TallyOutcome::Rejected
+            }
+        }
+        None => TallyOutcome::Rejected,
+    };
+
+    TallyResult {
+        total_yay_power,
+        total_nay_power,
+        result,
+    }
+}
+
+/// The minimum Yay power required for a proposal to pass, given the total
+/// active stake at its start epoch and the `min_proposal_threshold`
+/// parameter.
+///
+/// `min_proposal_threshold` is expressed in thousandths of the total
+/// active stake (e.g. 666 for approximately two-thirds), rounded up.
+/// Intermediate arithmetic is done in `u128` so this cannot overflow even
+/// for the largest representable stake.
+fn required_yay_power(
+    total_active_stake: Amount,
+    min_proposal_threshold: Amount,
+) -> Amount {
+    let total_active_stake = u128::from(u64::from(total_active_stake));
+    let min_proposal_threshold = u128::from(u64::from(min_proposal_threshold));
+    let required = total_active_stake
+        .saturating_mul(min_proposal_threshold)
+        .saturating_add(999)
+        / 1000;
+    Amount::from(u64::try_from(required).unwrap_or(u64::MAX))
+}
+
+fn settle_funds<D, H>(
+    storage: &mut Storage<D, H>,
+    proposal_id: u64,
+    result: TallyOutcome,
+) where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let funds_key = gov_storage::get_funds_key(proposal_id);
+    let author_key = gov_storage::get_author_key(proposal_id);
+    let funds: Amount = read(storage, &funds_key).unwrap_or_default();
+    let author: Option<Address> = read(storage, &author_key);
+
+    // The deposit always leaves the governance escrow: on passing it is
+    // returned to the author, on rejection it is burned outright.
+    debit(storage, &super::ADDRESS, funds);
+    if let (TallyOutcome::Passed, Some(author)) = (result, author) {
+        credit(storage, &author, funds);
+    }
+}
+
+/// Whether a proposal's voting period has already been tallied and
+/// settled, i.e. `write_tally_result` has already run for it.
+fn has_tally_result<D, H>(storage: &Storage<D, H>, proposal_id: u64) -> bool
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let result_key = gov_storage::get_proposal_result_key(proposal_id);
+    read::<TallyOutcome, _, _>(storage, &result_key).is_some()
+}
+
+/// Whether a proposal's grace-epoch action has already run.
+fn has_been_executed<D, H>(storage: &Storage<D, H>, proposal_id: u64) -> bool
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let executed_key = gov_storage::get_proposal_executed_key(proposal_id);
+    read::<bool, _, _>(storage, &executed_key).unwrap_or(false)
+}
+
+/// Record that a proposal's grace-epoch action has run, so later blocks
+/// of the same grace epoch don't run it again.
+fn mark_executed<D, H>(storage: &mut Storage<D, H>, proposal_id: u64)
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let executed_key = gov_storage::get_proposal_executed_key(proposal_id);
+    let _ = storage.write(
+        &executed_key,
+        true.try_to_vec()
+            .expect("serializing a bool always succeeds"),
+    );
+}
+
+/// Persist the tally outcome of a proposal whose voting period just ended,
+/// so it can be checked again once the proposal reaches its grace epoch.
+fn write_tally_result<D, H>(
+    storage: &mut Storage<D, H>,
+    proposal_id: u64,
+    result: TallyOutcome,
+) where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let result_key = gov_storage::get_proposal_result_key(proposal_id);
+    let _ = storage.write(
+        &result_key,
+        result
+            .try_to_vec()
+            .expect("serializing a TallyOutcome always succeeds"),
+    );
+}
+
+fn execute_proposal<D, H>(storage: &mut Storage<D, H>, proposal_id: u64)
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let result_key = gov_storage::get_proposal_result_key(proposal_id);
+    let result: Option<TallyOutcome> = read(storage, &result_key);
+    if result != Some(TallyOutcome::Passed) {
+        // A proposal that was rejected, or whose tally was never
+        // persisted, never gets executed.
+        return;
+    }
+
+    let proposal_type_key = gov_storage::get_proposal_type_key(proposal_id);
+    let proposal_type: Option<ProposalType> =
+        read(storage, &proposal_type_key);
+    match proposal_type {
+        Some(ProposalType::Default) => {
+            let proposal_code_key =
+                gov_storage::get_proposal_code_key(proposal_id);
+            if let Some(code) =
+                read::<Vec<u8>, _, _>(storage, &proposal_code_key)
+            {
+                storage.run_wasm_proposal_code(proposal_id, &code);
+            }
+        }
+        Some(ProposalType::PgfSteward | ProposalType::PgfFunding) => {
+            authorize_pgf_updates(storage, proposal_id);
+        }
+        Some(ProposalType::EthBridge) | None => {}
+    }
+}
+
+/// Record `proposal_id` as the proposal currently authorizing PGF
+/// storage writes, so `GovernanceVp` can confirm a PGF tx is acting on
+/// an approved `PgfSteward`/`PgfFunding` proposal rather than merely
+/// being signed by the governance address.
+pub fn authorize_pgf_updates<D, H>(
+    storage: &mut Storage<D, H>,
+    proposal_id: u64,
+) where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let authorizing_key = gov_storage::get_pgf_authorizing_proposal_key();
+    let _ = storage.write(
+        &authorizing_key,
+        proposal_id
+            .try_to_vec()
+            .expect("serializing a u64 always succeeds"),
+    );
+}
+
+fn proposals_ending_at<D, H>(
+    storage: &Storage<D, H>,
+    epoch: Epoch,
+) -> Vec<u64>
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    filter_proposals_by_epoch(storage, epoch, |id| {
+        gov_storage::get_voting_end_epoch_key(id)
+    })
+}
+
+fn proposals_at_grace_epoch<D, H>(
+    storage: &Storage<D, H>,
+    epoch: Epoch,
+) -> Vec<u64>
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    filter_proposals_by_epoch(storage, epoch, |id| {
+        gov_storage::get_grace_epoch_key(id)
+    })
+}
+
+fn filter_proposals_by_epoch<D, H>(
+    storage: &Storage<D, H>,
+    epoch: Epoch,
+    key_for: impl Fn(u64) -> Key,
+) -> Vec<u64>
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let counter_key = gov_storage::get_counter_key();
+    let counter: u64 = read(storage, &counter_key).unwrap_or_default();
+
+    (0..counter)
+        .filter(|&proposal_id| {
+            let key = key_for(proposal_id);
+            read::<u64, _, _>(storage, &key) == Some(epoch.0)
+        })
+        .collect()
+}
+
+fn read_votes<D, H>(
+    storage: &Storage<D, H>,
+    proposal_id: u64,
+) -> BTreeMap<Address, ProposalVote>
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let prefix = gov_storage::get_proposal_vote_prefix_key(proposal_id);
+    let (iter, _gas) = storage.iter_prefix(&prefix);
+    iter.filter_map(|(key, bytes, _gas)| {
+        let key = Key::parse(key).ok()?;
+        let voter = match key.get_at(4) {
+            Some(DbKeySeg::AddressSeg(voter)) => voter.clone(),
+            _ => return None,
+        };
+        let vote = ProposalVote::try_from_slice(&bytes).ok()?;
+        Some((voter, vote))
+    })
+    .collect()
+}
+
+fn read<T, D, H>(storage: &Storage<D, H>, key: &Key) -> Option<T>
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    T: BorshDeserialize,
+{
+    let (bytes, _gas) = storage.read(key).ok()?;
+    T::try_from_slice(&bytes?).ok()
+}
+
+fn credit<D, H>(storage: &mut Storage<D, H>, recipient: &Address, amount: Amount)
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let balance_key = crate::types::token::balance_key(&m1t(), recipient);
+    let pre_balance: Amount = read(storage, &balance_key).unwrap_or_default();
+    let post_balance = pre_balance + amount;
+    let _ = storage.write(
+        &balance_key,
+        post_balance
+            .try_to_vec()
+            .expect("serializing an Amount always succeeds"),
+    );
+}
+
+fn debit<D, H>(storage: &mut Storage<D, H>, holder: &Address, amount: Amount)
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let balance_key = crate::types::token::balance_key(&m1t(), holder);
+    let pre_balance: Amount = read(storage, &balance_key).unwrap_or_default();
+    let post_balance = pre_balance - amount;
+    let _ = storage.write(
+        &balance_key,
+        post_balance
+            .try_to_vec()
+            .expect("serializing an Amount always succeeds"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_thirds_threshold_rounds_up() {
+        // 999 * 666 / 1000 = 665.334, which must round up to 666 rather
+        // than be truncated down to 665.
+        let required = required_yay_power(
+            Amount::from(999),
+            Amount::from(666),
+        );
+        assert_eq!(required, Amount::from(666));
+    }
+
+    #[test]
+    fn threshold_does_not_overflow_for_a_large_stake() {
+        let required = required_yay_power(
+            Amount::from(u64::MAX),
+            Amount::from(1000),
+        );
+        assert_eq!(required, Amount::from(u64::MAX));
+    }
+
+    #[test]
+    fn zero_threshold_requires_no_yay_power() {
+        let required =
+            required_yay_power(Amount::from(1_000_000), Amount::from(0));
+        assert_eq!(required, Amount::from(0));
+    }
+}
@@ -1,9 +1,15 @@
 //! Protocol parameters
 
+/// tallying and execution of proposals at block finalization
+pub mod finalize_block;
 /// governance parameters
 pub mod parameters;
+/// read-only queries over governance storage
+pub mod queries;
 /// governance storage
 pub mod storage;
+/// `StorageReader` backed by the WASM VP host environment
+pub mod wasm_context;
 
 use std::collections::BTreeSet;
 
@@ -12,9 +18,11 @@ use thiserror::Error;
 
 use self::storage as gov_storage;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
+use crate::ledger::pos;
 use crate::ledger::storage::{self as ledger_storage, StorageHasher};
 use crate::types::address::{xan as m1t, Address, InternalAddress};
-use crate::types::storage::{DbKeySeg, Key};
+use crate::types::governance::ProposalVote;
+use crate::types::storage::{DbKeySeg, Epoch, Key};
 use crate::types::token as token_storage;
 use crate::types::token::Amount;
 use crate::vm::WasmCacheAccess;
@@ -31,6 +39,8 @@ pub enum Error {
     NativeVpDeserializationError(std::io::Error),
     #[error("Native VP error non-existing key: {0}")]
     NativeVpNonExistingKeyError(String),
+    #[error("WASM VP host environment error: {0}")]
+    WasmHostEnvError(crate::vm::host_env::vp::Error),
 }
 
 impl From<native_vp::Error> for Error {
@@ -39,9 +49,64 @@ impl From<native_vp::Error> for Error {
     }
 }
 
+impl From<crate::vm::host_env::vp::Error> for Error {
+    fn from(err: crate::vm::host_env::vp::Error) -> Self {
+        Self::WasmHostEnvError(err)
+    }
+}
+
 /// Governance functions result
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Abstracts the handful of storage operations the governance validation
+/// rules need, so `validate_tx` and its helpers can run unchanged against
+/// the native ledger context or a WASM VP host environment.
+pub trait StorageReader {
+    /// Read a value at `key` as it stood before this transaction.
+    fn read_pre(&self, key: &Key) -> Result<Option<Vec<u8>>>;
+    /// Read a value at `key` as left by this transaction.
+    fn read_post(&self, key: &Key) -> Result<Option<Vec<u8>>>;
+    /// Check whether `key` existed before this transaction.
+    fn has_key_pre(&self, key: &Key) -> Result<bool>;
+    /// The epoch of the block being validated.
+    fn get_block_epoch(&self) -> Result<Epoch>;
+    /// Whether `address` is a validator at `epoch`.
+    fn is_validator(&self, address: &Address, epoch: Epoch) -> Result<bool>;
+    /// Whether `address` is a delegator at `epoch`.
+    fn is_delegator(&self, address: &Address, epoch: Epoch) -> Result<bool>;
+}
+
+impl<'a, DB, H, CA> StorageReader for Ctx<'a, DB, H, CA>
+where
+    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    fn read_pre(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        Ctx::read_pre(self, key).map_err(Error::from)
+    }
+
+    fn read_post(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        Ctx::read_post(self, key).map_err(Error::from)
+    }
+
+    fn has_key_pre(&self, key: &Key) -> Result<bool> {
+        Ctx::has_key_pre(self, key).map_err(Error::from)
+    }
+
+    fn get_block_epoch(&self) -> Result<Epoch> {
+        Ctx::get_block_epoch(self).map_err(Error::from)
+    }
+
+    fn is_validator(&self, address: &Address, epoch: Epoch) -> Result<bool> {
+        Ok(pos::is_validator(self, address, epoch))
+    }
+
+    fn is_delegator(&self, address: &Address, epoch: Epoch) -> Result<bool> {
+        Ok(pos::is_delegator(self, address, epoch))
+    }
+}
+
 /// Governance VP
 pub struct GovernanceVp<'a, DB, H, CA>
 where
@@ -65,283 +130,700 @@ where
 
     fn validate_tx(
         &self,
-        _tx_data: &[u8],
+        tx_data: &[u8],
         keys_changed: &BTreeSet<Key>,
         verifiers: &BTreeSet<Address>,
     ) -> Result<bool> {
-        if !is_valid_key_set(&self.ctx, keys_changed) {
-            return Ok(false);
-        };
+        validate_tx(&self.ctx, tx_data, keys_changed, verifiers)
+    }
+}
 
-        let result = keys_changed.iter().all(|key| {
-            let proposal_id = get_id(key);
-
-            let key_type: KeyType = key.into();
-            match (key_type, proposal_id) {
-                (KeyType::VOTE, Some(_)) => false,
-                (KeyType::CONTENT, Some(proposal_id)) => {
-                    let content_key: Key =
-                        gov_storage::get_content_key(proposal_id);
-                    let max_content_length_parameter_key =
-                        gov_storage::get_max_proposal_content_key();
-                    let max_content_length = read(
-                        &self.ctx,
-                        &max_content_length_parameter_key,
-                        ReadType::PRE,
-                    )
-                    .ok();
-                    let has_pre_content =
-                        self.ctx.has_key_pre(&content_key).ok();
-                    let post_content =
-                        self.ctx.read_post(&content_key).unwrap();
-                    match (has_pre_content, post_content, max_content_length) {
-                        (
-                            Some(has_pre_content),
-                            Some(post_content),
-                            Some(max_content_length),
-                        ) => {
-                            !has_pre_content
-                                && post_content.len() < max_content_length
-                        }
-                        _ => false,
+/// Validate a governance transaction's storage changes against the
+/// governance VP's rules. Generic over any `StorageReader`, so the
+/// same validation runs whether `context` is backed by the native
+/// ledger or a WASM VP host environment.
+pub fn validate_tx<CTX: StorageReader>(
+    context: &CTX,
+    _tx_data: &[u8],
+    keys_changed: &BTreeSet<Key>,
+    verifiers: &BTreeSet<Address>,
+) -> Result<bool> {
+    if !is_valid_key_set(context, keys_changed) {
+        return Ok(false);
+    };
+
+    let result = keys_changed.iter().all(|key| {
+        let proposal_id = get_id(key);
+
+        let key_type: KeyType = key.into();
+        match (key_type, proposal_id) {
+            (KeyType::VOTE, Some(proposal_id)) => {
+                let voter = match get_voter_address(key) {
+                    Some(voter) => voter,
+                    None => return false,
+                };
+                let start_epoch_key =
+                    gov_storage::get_voting_start_epoch_key(proposal_id);
+                let end_epoch_key =
+                    gov_storage::get_voting_end_epoch_key(proposal_id);
+                let start_epoch: Option<u64> =
+                    read(context, &start_epoch_key, ReadType::PRE).ok();
+                let end_epoch: Option<u64> =
+                    read(context, &end_epoch_key, ReadType::PRE).ok();
+                let current_epoch = context.get_block_epoch().ok();
+                let has_pre_vote = context.has_key_pre(key).ok();
+                let vote: Option<ProposalVote> =
+                    read(context, key, ReadType::POST).ok();
+                match (
+                    start_epoch,
+                    end_epoch,
+                    current_epoch,
+                    has_pre_vote,
+                    vote,
+                ) {
+                    (
+                        Some(start_epoch),
+                        Some(end_epoch),
+                        Some(current_epoch),
+                        Some(has_pre_vote),
+                        Some(_vote),
+                    ) => {
+                        let current_epoch = current_epoch.0;
+                        let is_in_voting_window = is_valid_vote_window(
+                            current_epoch,
+                            start_epoch,
+                            end_epoch,
+                        );
+                        // A voter may cast a vote once per proposal: a
+                        // vote key that already existed before this tx is
+                        // rejected, so changing an existing vote is never
+                        // allowed, only casting a fresh one.
+                        // Routed through `StorageReader` (rather than
+                        // calling `pos::` directly) so this also runs
+                        // under a WASM VP host environment.
+                        let is_delegator_or_validator = context
+                            .is_validator(&voter, Epoch(start_epoch))
+                            .unwrap_or(false)
+                            || context
+                                .is_delegator(&voter, Epoch(start_epoch))
+                                .unwrap_or(false);
+                        is_in_voting_window
+                            && !has_pre_vote
+                            && verifiers.contains(&voter)
+                            && is_delegator_or_validator
                     }
+                    _ => false,
                 }
-                (KeyType::PROPOSAL_CODE, Some(proposal_id)) => {
-                    let proposal_code_key =
-                        gov_storage::get_proposal_code_key(proposal_id);
-                    let max_proposal_code_size_parameter_key =
-                        gov_storage::get_max_proposal_code_size_key();
-                    let max_proposal_code_size: Option<usize> = read(
-                        &self.ctx,
-                        &max_proposal_code_size_parameter_key,
-                        ReadType::PRE,
-                    )
-                    .ok();
-                    let has_pre_proposal_code =
-                        self.ctx.has_key_pre(&proposal_code_key).ok();
-                    let post_proposal_code: Option<Vec<u8>> =
-                        read(&self.ctx, &proposal_code_key, ReadType::POST)
-                            .ok();
-                    match (
-                        has_pre_proposal_code,
-                        post_proposal_code,
-                        max_proposal_code_size,
-                    ) {
-                        (
-                            Some(has_pre_proposal_code),
-                            Some(post_proposal_code),
-                            Some(max_proposal_code_size),
-                        ) => {
-                            !has_pre_proposal_code
-                                && post_proposal_code.len()
-                                    < max_proposal_code_size
-                        }
-                        _ => false,
+            }
+            (KeyType::CONTENT, Some(proposal_id)) => {
+                let content_key: Key =
+                    gov_storage::get_content_key(proposal_id);
+                let max_content_length_parameter_key =
+                    gov_storage::get_max_proposal_content_key();
+                let max_content_length = read(
+                    context,
+                    &max_content_length_parameter_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                let has_pre_content =
+                    context.has_key_pre(&content_key).ok();
+                let post_content =
+                    context.read_post(&content_key).unwrap();
+                match (has_pre_content, post_content, max_content_length) {
+                    (
+                        Some(has_pre_content),
+                        Some(post_content),
+                        Some(max_content_length),
+                    ) => {
+                        !has_pre_content
+                            && post_content.len() < max_content_length
                     }
+                    _ => false,
                 }
-                (KeyType::GRACE_EPOCH, Some(proposal_id)) => {
-                    let end_epoch_key =
-                        gov_storage::get_voting_end_epoch_key(proposal_id);
-                    let grace_epoch_key =
-                        gov_storage::get_grace_epoch_key(proposal_id);
-                    let end_epoch: Option<u64> =
-                        read(&self.ctx, &end_epoch_key, ReadType::POST).ok();
-                    let grace_epoch: Option<u64> =
-                        read(&self.ctx, &grace_epoch_key, ReadType::POST).ok();
-                    let has_pre_grace_epoch =
-                        self.ctx.has_key_pre(&grace_epoch_key).ok();
-                    match (has_pre_grace_epoch, grace_epoch, end_epoch) {
-                        (
-                            Some(has_pre_grace_epoch),
-                            Some(grace_epoch),
-                            Some(end_epoch),
-                        ) => !has_pre_grace_epoch && end_epoch < grace_epoch,
-                        _ => false,
+            }
+            (KeyType::PROPOSAL_TYPE, Some(proposal_id)) => {
+                let proposal_type_key =
+                    gov_storage::get_proposal_type_key(proposal_id);
+                let has_pre_proposal_type =
+                    context.has_key_pre(&proposal_type_key).ok();
+                let post_proposal_type: Option<ProposalType> = read(
+                    context,
+                    &proposal_type_key,
+                    ReadType::POST,
+                )
+                .ok();
+                match (has_pre_proposal_type, post_proposal_type) {
+                    (Some(has_pre_proposal_type), Some(_)) => {
+                        !has_pre_proposal_type
                     }
+                    _ => false,
                 }
-                (
-                    KeyType::START_EPOCH | KeyType::END_EPOCH,
-                    Some(proposal_id),
-                ) => {
-                    let start_epoch_key =
-                        gov_storage::get_voting_start_epoch_key(proposal_id);
-                    let end_epoch_key =
-                        gov_storage::get_voting_end_epoch_key(proposal_id);
-                    let start_epoch: Option<u64> =
-                        read(&self.ctx, &start_epoch_key, ReadType::POST).ok();
-                    let end_epoch: Option<u64> =
-                        read(&self.ctx, &end_epoch_key, ReadType::POST).ok();
-                    let current_epoch = self.ctx.get_block_epoch().ok();
-                    let min_period_parameter_key =
-                        gov_storage::get_min_proposal_period_key();
-                    let min_period: Option<u64> = read(
-                        &self.ctx,
-                        &min_period_parameter_key,
-                        ReadType::PRE,
-                    )
-                    .ok();
-                    let has_pre_start_epoch =
-                        self.ctx.has_key_pre(&start_epoch_key).ok();
-                    let has_pre_end_epoch =
-                        self.ctx.has_key_pre(&end_epoch_key).ok();
-                    match (
-                        has_pre_start_epoch,
-                        has_pre_end_epoch,
-                        min_period,
-                        start_epoch,
-                        end_epoch,
-                        current_epoch,
-                    ) {
-                        (
-                            Some(has_pre_start_epoch),
-                            Some(has_pre_end_epoch),
-                            Some(min_period),
-                            Some(start_epoch),
-                            Some(end_epoch),
-                            Some(current_epoch),
-                        ) => {
-                            let current_epoch = current_epoch.0;
-                            if end_epoch <= start_epoch
-                                || start_epoch <= current_epoch
-                            {
-                                return false;
-                            }
-                            !has_pre_start_epoch
-                                && !has_pre_end_epoch
-                                && start_epoch < end_epoch
-                                && (end_epoch - start_epoch) % min_period == 0
-                                && start_epoch - current_epoch >= min_period
-                        }
-                        _ => false,
+            }
+            (KeyType::PROPOSAL_CODE, Some(proposal_id)) => {
+                let proposal_type_key =
+                    gov_storage::get_proposal_type_key(proposal_id);
+                let proposal_type: Option<ProposalType> = read(
+                    context,
+                    &proposal_type_key,
+                    ReadType::POST,
+                )
+                .ok();
+                if !matches!(proposal_type, Some(ProposalType::Default)) {
+                    return false;
+                }
+                let proposal_code_key =
+                    gov_storage::get_proposal_code_key(proposal_id);
+                let max_proposal_code_size_parameter_key =
+                    gov_storage::get_max_proposal_code_size_key();
+                let max_proposal_code_size: Option<usize> = read(
+                    context,
+                    &max_proposal_code_size_parameter_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                let has_pre_proposal_code =
+                    context.has_key_pre(&proposal_code_key).ok();
+                let post_proposal_code: Option<Vec<u8>> =
+                    read(context, &proposal_code_key, ReadType::POST)
+                        .ok();
+                match (
+                    has_pre_proposal_code,
+                    post_proposal_code,
+                    max_proposal_code_size,
+                ) {
+                    (
+                        Some(has_pre_proposal_code),
+                        Some(post_proposal_code),
+                        Some(max_proposal_code_size),
+                    ) => {
+                        !has_pre_proposal_code
+                            && post_proposal_code.len()
+                                < max_proposal_code_size
                     }
+                    _ => false,
                 }
-                (KeyType::FUNDS, Some(proposal_id)) => {
-                    let funds_key = gov_storage::get_funds_key(proposal_id);
-                    let balance_key =
-                        token_storage::balance_key(&m1t(), &ADDRESS);
-                    let min_funds_parameter_key =
-                        gov_storage::get_min_proposal_fund_key();
-                    let min_funds_parameter: Option<Amount> = read(
-                        &self.ctx,
-                        &min_funds_parameter_key,
-                        ReadType::PRE,
-                    )
-                    .ok();
-                    let pre_balance: Option<Amount> =
-                        read(&self.ctx, &balance_key, ReadType::PRE).ok();
-                    let post_balance: Option<Amount> =
-                        read(&self.ctx, &balance_key, ReadType::POST).ok();
-                    let post_funds: Option<Amount> =
-                        read(&self.ctx, &funds_key, ReadType::POST).ok();
-                    match (
-                        min_funds_parameter,
-                        pre_balance,
-                        post_balance,
-                        post_funds,
-                    ) {
-                        (
-                            Some(min_funds_parameter),
-                            Some(pre_balance),
-                            Some(post_balance),
-                            Some(post_funds),
-                        ) => {
-                            post_funds >= min_funds_parameter
-                                && post_balance - pre_balance == post_funds
-                        }
-                        _ => false,
+            }
+            (KeyType::PROPOSAL_PAYLOAD, Some(proposal_id)) => {
+                let proposal_type_key =
+                    gov_storage::get_proposal_type_key(proposal_id);
+                let proposal_type: Option<ProposalType> = read(
+                    context,
+                    &proposal_type_key,
+                    ReadType::POST,
+                )
+                .ok();
+                let payload_key =
+                    gov_storage::get_proposal_payload_key(proposal_id);
+                let has_pre_payload =
+                    context.has_key_pre(&payload_key).ok();
+                match (proposal_type, has_pre_payload) {
+                    (
+                        Some(
+                            ProposalType::PgfSteward
+                            | ProposalType::PgfFunding,
+                        ),
+                        Some(has_pre_payload),
+                    ) => {
+                        let targets: Option<Vec<Address>> = read(
+                            context,
+                            &payload_key,
+                            ReadType::POST,
+                        )
+                        .ok();
+                        !has_pre_payload
+                            && targets
+                                .map(|targets| !targets.is_empty())
+                                .unwrap_or(false)
+                    }
+                    (
+                        Some(ProposalType::EthBridge),
+                        Some(has_pre_payload),
+                    ) => {
+                        let action: Option<EthBridgeAction> = read(
+                            context,
+                            &payload_key,
+                            ReadType::POST,
+                        )
+                        .ok();
+                        !has_pre_payload
+                            && action
+                                .map(|action| action.is_well_formed())
+                                .unwrap_or(false)
                     }
+                    _ => false,
                 }
-                (KeyType::AUTHOR, Some(proposal_id)) => {
-                    let author_key = gov_storage::get_author_key(proposal_id);
-                    let author =
-                        read(&self.ctx, &author_key, ReadType::POST).ok();
-                    let has_pre_author = self.ctx.has_key_pre(&author_key).ok();
-                    match (has_pre_author, author) {
-                        (Some(has_pre_author), Some(author)) => {
-                            !has_pre_author && verifiers.contains(&author)
-                        }
-                        _ => false,
+            }
+            (KeyType::GRACE_EPOCH, Some(proposal_id)) => {
+                let end_epoch_key =
+                    gov_storage::get_voting_end_epoch_key(proposal_id);
+                let grace_epoch_key =
+                    gov_storage::get_grace_epoch_key(proposal_id);
+                let end_epoch: Option<u64> =
+                    read(context, &end_epoch_key, ReadType::POST).ok();
+                let grace_epoch: Option<u64> =
+                    read(context, &grace_epoch_key, ReadType::POST).ok();
+                let has_pre_grace_epoch =
+                    context.has_key_pre(&grace_epoch_key).ok();
+                let min_grace_epochs_key =
+                    gov_storage::get_min_grace_epochs_key();
+                let min_grace_epochs: Option<u64> = read(
+                    context,
+                    &min_grace_epochs_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                // Every newly queued proposal must raise the
+                // congestion-window tracker to at least its own grace
+                // epoch, or the anti-congestion check in the
+                // start/end-epoch arm below would never see it and
+                // would stay permanently inert. The tracker holds the
+                // *maximum* grace epoch among all pending proposals, not
+                // just the most recently queued one, so queuing a
+                // proposal with an earlier grace epoch can never clobber
+                // a still-pending, later-executing one.
+                let pending_tracker_key =
+                    gov_storage::get_last_proposal_grace_epoch_key();
+                let pre_pending_grace_epoch: Option<u64> = read(
+                    context,
+                    &pending_tracker_key,
+                    ReadType::PRE,
+                )
+                .ok()
+                .or(Some(0));
+                let post_pending_grace_epoch: Option<u64> = read(
+                    context,
+                    &pending_tracker_key,
+                    ReadType::POST,
+                )
+                .ok();
+                match (
+                    has_pre_grace_epoch,
+                    grace_epoch,
+                    end_epoch,
+                    min_grace_epochs,
+                    pre_pending_grace_epoch,
+                    post_pending_grace_epoch,
+                ) {
+                    (
+                        Some(has_pre_grace_epoch),
+                        Some(grace_epoch),
+                        Some(end_epoch),
+                        Some(min_grace_epochs),
+                        Some(pre_pending_grace_epoch),
+                        Some(post_pending_grace_epoch),
+                    ) => {
+                        !has_pre_grace_epoch
+                            && end_epoch < grace_epoch
+                            && grace_epoch - end_epoch >= min_grace_epochs
+                            && post_pending_grace_epoch
+                                == grace_epoch.max(pre_pending_grace_epoch)
                     }
+                    _ => false,
                 }
-                (KeyType::COUNTER, _) => {
-                    let counter_key = gov_storage::get_counter_key();
-                    let pre_counter: Option<u64> =
-                        read(&self.ctx, &counter_key, ReadType::PRE).ok();
-                    let post_counter: Option<u64> =
-                        read(&self.ctx, &counter_key, ReadType::POST).ok();
-                    match (pre_counter, post_counter) {
-                        (Some(pre_counter), Some(post_counter)) => {
-                            pre_counter + 1 == post_counter
+            }
+            (
+                KeyType::START_EPOCH | KeyType::END_EPOCH,
+                Some(proposal_id),
+            ) => {
+                let start_epoch_key =
+                    gov_storage::get_voting_start_epoch_key(proposal_id);
+                let end_epoch_key =
+                    gov_storage::get_voting_end_epoch_key(proposal_id);
+                let start_epoch: Option<u64> =
+                    read(context, &start_epoch_key, ReadType::POST).ok();
+                let end_epoch: Option<u64> =
+                    read(context, &end_epoch_key, ReadType::POST).ok();
+                let current_epoch = context.get_block_epoch().ok();
+                let min_period_parameter_key =
+                    gov_storage::get_min_proposal_period_key();
+                let min_period: Option<u64> = read(
+                    context,
+                    &min_period_parameter_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                let max_proposal_period_key =
+                    gov_storage::get_max_proposal_period_key();
+                let max_proposal_period: Option<u64> = read(
+                    context,
+                    &max_proposal_period_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                let min_grace_epochs_key =
+                    gov_storage::get_min_grace_epochs_key();
+                let min_grace_epochs: Option<u64> = read(
+                    context,
+                    &min_grace_epochs_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                let last_queued_grace_epoch_key =
+                    gov_storage::get_last_proposal_grace_epoch_key();
+                let last_queued_grace_epoch: Option<u64> = read(
+                    context,
+                    &last_queued_grace_epoch_key,
+                    ReadType::PRE,
+                )
+                .ok()
+                .or(Some(0));
+                let has_pre_start_epoch =
+                    context.has_key_pre(&start_epoch_key).ok();
+                let has_pre_end_epoch =
+                    context.has_key_pre(&end_epoch_key).ok();
+                match (
+                    has_pre_start_epoch,
+                    has_pre_end_epoch,
+                    min_period,
+                    max_proposal_period,
+                    min_grace_epochs,
+                    last_queued_grace_epoch,
+                    start_epoch,
+                    end_epoch,
+                    current_epoch,
+                ) {
+                    (
+                        Some(has_pre_start_epoch),
+                        Some(has_pre_end_epoch),
+                        Some(min_period),
+                        Some(max_proposal_period),
+                        Some(min_grace_epochs),
+                        Some(last_queued_grace_epoch),
+                        Some(start_epoch),
+                        Some(end_epoch),
+                        Some(current_epoch),
+                    ) => {
+                        let current_epoch = current_epoch.0;
+                        if end_epoch <= start_epoch
+                            || start_epoch <= current_epoch
+                        {
+                            return false;
                         }
-                        _ => false,
+                        // Do not allow queuing a new proposal that would
+                        // land on an already-congested grace epoch
+                        // window.
+                        let is_outside_congestion_window =
+                            is_outside_congestion_window(
+                                current_epoch,
+                                last_queued_grace_epoch,
+                                min_grace_epochs,
+                            );
+                        !has_pre_start_epoch
+                            && !has_pre_end_epoch
+                            && start_epoch < end_epoch
+                            && (end_epoch - start_epoch) % min_period == 0
+                            && start_epoch - current_epoch >= min_period
+                            && end_epoch - start_epoch <= max_proposal_period
+                            && is_outside_congestion_window
                     }
+                    _ => false,
                 }
-                (KeyType::BALANCE, _) => {
-                    let balance_key =
-                        token_storage::balance_key(&m1t(), &ADDRESS);
-                    let min_funds_parameter_key =
-                        gov_storage::get_min_proposal_fund_key();
-                    let min_funds_parameter: Option<Amount> = read(
-                        &self.ctx,
-                        &min_funds_parameter_key,
-                        ReadType::PRE,
-                    )
-                    .ok();
-                    let pre_balance: Option<Amount> =
-                        read(&self.ctx, &balance_key, ReadType::PRE).ok();
-                    let post_balance: Option<Amount> =
-                        read(&self.ctx, &balance_key, ReadType::POST).ok();
-                    match (min_funds_parameter, pre_balance, post_balance) {
+            }
+            (KeyType::FUNDS, Some(proposal_id)) => {
+                let funds_key = gov_storage::get_funds_key(proposal_id);
+                let balance_key =
+                    token_storage::balance_key(&m1t(), &ADDRESS);
+                let min_funds_parameter_key =
+                    gov_storage::get_min_proposal_fund_key();
+                let min_funds_parameter: Option<Amount> = read(
+                    context,
+                    &min_funds_parameter_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                let pre_balance: Option<Amount> =
+                    read(context, &balance_key, ReadType::PRE).ok();
+                let post_balance: Option<Amount> =
+                    read(context, &balance_key, ReadType::POST).ok();
+                let post_funds: Option<Amount> =
+                    read(context, &funds_key, ReadType::POST).ok();
+                match (
+                    min_funds_parameter,
+                    pre_balance,
+                    post_balance,
+                    post_funds,
+                ) {
+                    (
+                        Some(min_funds_parameter),
+                        Some(pre_balance),
+                        Some(post_balance),
+                        Some(post_funds),
+                    ) => {
+                        post_funds >= min_funds_parameter
+                            && post_balance - pre_balance == post_funds
+                    }
+                    _ => false,
+                }
+            }
+            (KeyType::AUTHOR, Some(proposal_id)) => {
+                let author_key = gov_storage::get_author_key(proposal_id);
+                let author =
+                    read(context, &author_key, ReadType::POST).ok();
+                let has_pre_author = context.has_key_pre(&author_key).ok();
+                match (has_pre_author, author) {
+                    (Some(has_pre_author), Some(author)) => {
+                        !has_pre_author && verifiers.contains(&author)
+                    }
+                    _ => false,
+                }
+            }
+            (KeyType::COUNTER, _) => {
+                let counter_key = gov_storage::get_counter_key();
+                let pre_counter: Option<u64> =
+                    read(context, &counter_key, ReadType::PRE).ok();
+                let post_counter: Option<u64> =
+                    read(context, &counter_key, ReadType::POST).ok();
+                match (pre_counter, post_counter) {
+                    (Some(pre_counter), Some(post_counter)) => {
+                        pre_counter + 1 == post_counter
+                    }
+                    _ => false,
+                }
+            }
+            (KeyType::BALANCE, _) => {
+                let balance_key =
+                    token_storage::balance_key(&m1t(), &ADDRESS);
+                let min_funds_parameter_key =
+                    gov_storage::get_min_proposal_fund_key();
+                let min_funds_parameter: Option<Amount> = read(
+                    context,
+                    &min_funds_parameter_key,
+                    ReadType::PRE,
+                )
+                .ok();
+                let pre_balance: Option<Amount> =
+                    read(context, &balance_key, ReadType::PRE).ok();
+                let post_balance: Option<Amount> =
+                    read(context, &balance_key, ReadType::POST).ok();
+                match (min_funds_parameter, pre_balance, post_balance) {
+                    (
+                        Some(min_funds_parameter),
+                        Some(pre_balance),
+                        Some(post_balance),
+                    ) => {
+                        post_balance > pre_balance
+                            && post_balance - pre_balance
+                                >= min_funds_parameter
+                    }
+                    (
+                        Some(min_funds_parameter),
+                        None,
+                        Some(post_balance),
+                    ) => post_balance >= min_funds_parameter,
+                    _ => false,
+                }
+            }
+            (KeyType::PGF, _) => {
+                // PGF recipients and stipends may only be written by the
+                // governance module itself (`verifiers.contains(&ADDRESS)`)
+                // while acting on a `PgfSteward`/`PgfFunding` proposal that
+                // actually passed, as recorded by `execute_proposal` in
+                // `get_pgf_authorizing_proposal_key`. That marker must
+                // also be cleared by this very tx, so a later, unrelated
+                // tx can never reuse an old proposal's authorization.
+                let authorizing_key =
+                    gov_storage::get_pgf_authorizing_proposal_key();
+                let authorizing_proposal_id: Option<u64> =
+                    read(context, &authorizing_key, ReadType::PRE).ok();
+                let authorization_consumed_by_this_tx = keys_changed
+                    .contains(&authorizing_key)
+                    && context
+                        .read_post(&authorizing_key)
+                        .ok()
+                        .flatten()
+                        .is_none();
+                let acts_on_an_approved_pgf_proposal =
+                    match authorizing_proposal_id {
+                        Some(proposal_id) => {
+                            let proposal_type: Option<ProposalType> = read(
+                                context,
+                                &gov_storage::get_proposal_type_key(
+                                    proposal_id,
+                                ),
+                                ReadType::PRE,
+                            )
+                            .ok();
+                            let tally_result: Option<
+                                finalize_block::TallyOutcome,
+                            > = read(
+                                context,
+                                &gov_storage::get_proposal_result_key(
+                                    proposal_id,
+                                ),
+                                ReadType::PRE,
+                            )
+                            .ok();
+                            authorization_consumed_by_this_tx
+                                && matches!(
+                                    proposal_type,
+                                    Some(
+                                        ProposalType::PgfSteward
+                                            | ProposalType::PgfFunding
+                                    )
+                                )
+                                && matches!(
+                                    tally_result,
+                                    Some(
+                                        finalize_block::TallyOutcome::Passed
+                                    )
+                                )
+                        }
+                        None => false,
+                    };
+                let is_writer_authorized = verifiers.contains(&ADDRESS)
+                    && acts_on_an_approved_pgf_proposal;
+
+                // The budget cap must hold whenever any stipend key
+                // changes, not only when the running total itself is
+                // rewritten, or a tx could raise individual stipends
+                // while leaving the total untouched.
+                let pgf_total_key =
+                    gov_storage::get_pgf_total_committed_key();
+                let budget_check = pgf_budget_check(context, keys_changed);
+
+                if key == &pgf_total_key {
+                    let post_total: Option<Amount> =
+                        read(context, &pgf_total_key, ReadType::POST).ok();
+                    match (post_total, budget_check) {
                         (
-                            Some(min_funds_parameter),
-                            Some(pre_balance),
-                            Some(post_balance),
+                            Some(post_total),
+                            Some((implied_post_total, within_budget)),
                         ) => {
-                            post_balance > pre_balance
-                                && post_balance - pre_balance
-                                    >= min_funds_parameter
+                            is_writer_authorized
+                                && within_budget
+                                && i128::from(u64::from(post_total))
+                                    == implied_post_total
                         }
-                        (
-                            Some(min_funds_parameter),
-                            None,
-                            Some(post_balance),
-                        ) => post_balance >= min_funds_parameter,
                         _ => false,
                     }
+                } else {
+                    let stipend: Option<Amount> =
+                        read(context, key, ReadType::POST).ok();
+                    let within_budget = matches!(
+                        budget_check,
+                        Some((_, true))
+                    );
+                    match stipend {
+                        // `Amount` is unsigned, so non-negativity is
+                        // already guaranteed by the type; no runtime
+                        // check is needed here.
+                        Some(_stipend) => {
+                            is_writer_authorized && within_budget
+                        }
+                        // Removing a recipient carries no stipend value
+                        // to validate, just the write authorization.
+                        None => is_writer_authorized && within_budget,
+                    }
                 }
-                (KeyType::PARAMETER, _) => false,
-                (KeyType::UNKNOWN, _) => false,
-                _ => false,
             }
-        });
-        Ok(result)
-    }
+            (KeyType::PENDING_GRACE_EPOCH_TRACKER, _) => {
+                // The GRACE_EPOCH arm above already enforces the exact
+                // value this key must take when a proposal is queued in
+                // the same tx; here we just require it never decreases,
+                // since it tracks a maximum.
+                let pending_tracker_key =
+                    gov_storage::get_last_proposal_grace_epoch_key();
+                let pre: Option<u64> = read(
+                    context,
+                    &pending_tracker_key,
+                    ReadType::PRE,
+                )
+                .ok()
+                .or(Some(0));
+                let post: Option<u64> =
+                    read(context, &pending_tracker_key, ReadType::POST)
+                        .ok();
+                match (pre, post) {
+                    (Some(pre), Some(post)) => post >= pre,
+                    _ => false,
+                }
+            }
+            (KeyType::PARAMETER, _) => false,
+            (KeyType::UNKNOWN, _) => false,
+            _ => false,
+        }
+    });
+    Ok(result)
+}
+
+/// The net change (post minus pre) across every PGF stipend key touched
+/// by this tx, excluding the running total itself. Used to confirm the
+/// running total is actually the sum of the individual stipends written
+/// in the same tx, rather than an unrelated, attacker-chosen value.
+fn net_stipend_delta<CTX: StorageReader>(
+    context: &CTX,
+    keys_changed: &BTreeSet<Key>,
+    pgf_total_key: &Key,
+) -> Option<i128> {
+    keys_changed
+        .iter()
+        .filter(|key| {
+            *key != pgf_total_key && gov_storage::is_pgf_key(key)
+        })
+        .try_fold(0i128, |acc, stipend_key| {
+            let pre: i128 = read::<Amount, _>(
+                context,
+                stipend_key,
+                ReadType::PRE,
+            )
+            .map(|amount| i128::from(u64::from(amount)))
+            .unwrap_or(0);
+            let post: i128 = read::<Amount, _>(
+                context,
+                stipend_key,
+                ReadType::POST,
+            )
+            .map(|amount| i128::from(u64::from(amount)))
+            .unwrap_or(0);
+            Some(acc + post - pre)
+        })
+}
+
+/// The implied total PGF commitment after this tx — the pre-tx total plus
+/// `net_stipend_delta` — and whether it stays within the configured
+/// budget. Computed independently of whether the running total key
+/// itself is among `keys_changed`, so the cap is enforced even when a
+/// tx only touches individual stipend keys. Returns `None` if the
+/// budget parameter can't be read; an absent stipend or running total
+/// counts as zero.
+fn pgf_budget_check<CTX: StorageReader>(
+    context: &CTX,
+    keys_changed: &BTreeSet<Key>,
+) -> Option<(i128, bool)> {
+    let pgf_total_key = gov_storage::get_pgf_total_committed_key();
+    let pgf_budget: Amount =
+        read(context, &gov_storage::get_pgf_budget_key(), ReadType::PRE)
+            .ok()?;
+    let pre_total: Amount =
+        read(context, &pgf_total_key, ReadType::PRE).unwrap_or_default();
+    let stipend_net_delta =
+        net_stipend_delta(context, keys_changed, &pgf_total_key)?;
+    let implied_post_total =
+        i128::from(u64::from(pre_total)) + stipend_net_delta;
+    let within_budget = implied_post_total >= 0
+        && implied_post_total <= i128::from(u64::from(pgf_budget));
+    Some((implied_post_total, within_budget))
 }
 
-fn is_valid_key_set<DB, H, CA>(
-    context: &Ctx<DB, H, CA>,
+fn is_valid_key_set<CTX: StorageReader>(
+    context: &CTX,
     keys: &BTreeSet<Key>,
-) -> bool
-where
-    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
-    H: 'static + StorageHasher,
-    CA: 'static + WasmCacheAccess,
-{
+) -> bool {
     if is_valid_proposal_init_key_set(context, keys) {
         return true;
     };
+    // A vote tx writes only vote keys, each checked by the VOTE arm.
+    if !keys.is_empty() && keys.iter().all(gov_storage::is_vote_key) {
+        return true;
+    }
     false
 }
 
-fn is_valid_proposal_init_key_set<DB, H, CA>(
-    context: &Ctx<DB, H, CA>,
+fn is_valid_proposal_init_key_set<CTX: StorageReader>(
+    context: &CTX,
     keys: &BTreeSet<Key>,
-) -> bool
-where
-    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
-    H: 'static + StorageHasher,
-    CA: 'static + WasmCacheAccess,
-{
+) -> bool {
     let counter_key = gov_storage::get_counter_key();
     let pre_counter: Option<u64> =
         read(context, &counter_key, ReadType::PRE).ok();
@@ -350,20 +832,109 @@ where
     }
     let counter = pre_counter.unwrap();
 
-    // Construct the set of expected keys
-    let mandatory_keys = BTreeSet::from([
+    let proposal_type_key = gov_storage::get_proposal_type_key(counter);
+    let proposal_type: Option<ProposalType> =
+        read(context, &proposal_type_key, ReadType::POST).ok();
+    let proposal_type = match proposal_type {
+        Some(proposal_type) => proposal_type,
+        None => return false,
+    };
+
+    // Construct the set of expected keys, common to every proposal type
+    let mut mandatory_keys = BTreeSet::from([
         counter_key,
+        proposal_type_key,
         gov_storage::get_content_key(counter),
         gov_storage::get_author_key(counter),
         gov_storage::get_funds_key(counter),
         gov_storage::get_voting_start_epoch_key(counter),
         gov_storage::get_voting_end_epoch_key(counter),
+        gov_storage::get_grace_epoch_key(counter),
+        gov_storage::get_last_proposal_grace_epoch_key(),
     ]);
 
+    // Add the type-specific payload key
+    if proposal_type_requires_payload_key(proposal_type) {
+        mandatory_keys.insert(gov_storage::get_proposal_payload_key(counter));
+    }
+
     // Check that expected set is a subset the actual one
     keys.is_superset(&mandatory_keys)
 }
 
+/// Whether a proposal of `proposal_type` must carry a payload key (the
+/// wasm code accompanying a default proposal is optional, but every other
+/// proposal type is meaningless without its payload).
+fn proposal_type_requires_payload_key(proposal_type: ProposalType) -> bool {
+    !matches!(proposal_type, ProposalType::Default)
+}
+
+/// The kind of a governance proposal, determining which storage keys are
+/// mandatory at proposal-initialization time and how the VP validates
+/// the proposal's payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum ProposalType {
+    /// A regular proposal, optionally carrying wasm code to run on
+    /// acceptance.
+    Default,
+    /// A proposal that updates the set of PGF stewards.
+    PgfSteward,
+    /// A continuous Public-Goods-Funding proposal.
+    PgfFunding,
+    /// A proposal carrying a signed Ethereum bridge action.
+    EthBridge,
+}
+
+/// A signed action to be relayed to the Ethereum bridge if the carrying
+/// proposal passes.
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct EthBridgeAction {
+    /// Borsh-encoded bridge action
+    pub action: Vec<u8>,
+    /// Signature authorizing the action
+    pub signature: Vec<u8>,
+}
+
+impl EthBridgeAction {
+    /// Check that the action carries non-empty action data and signature.
+    pub fn is_well_formed(&self) -> bool {
+        !self.action.is_empty() && !self.signature.is_empty()
+    }
+}
+
+fn get_voter_address(key: &Key) -> Option<Address> {
+    match key.get_at(4) {
+        Some(voter) => match voter {
+            DbKeySeg::AddressSeg(voter) => Some(voter.clone()),
+            DbKeySeg::StringSeg(_) => None,
+        },
+        None => None,
+    }
+}
+
+/// Whether `current_epoch` falls inside the inclusive `[start_epoch,
+/// end_epoch]` voting window of a proposal.
+fn is_valid_vote_window(
+    current_epoch: u64,
+    start_epoch: u64,
+    end_epoch: u64,
+) -> bool {
+    current_epoch >= start_epoch && current_epoch <= end_epoch
+}
+
+/// Whether a proposal queued at `current_epoch` falls outside the
+/// congestion window of the latest grace epoch among the queued
+/// proposals, i.e. either that grace epoch has already been reached, or
+/// it lies at least `min_grace_epochs` in the future.
+fn is_outside_congestion_window(
+    current_epoch: u64,
+    last_queued_grace_epoch: u64,
+    min_grace_epochs: u64,
+) -> bool {
+    current_epoch >= last_queued_grace_epoch
+        || last_queued_grace_epoch - current_epoch >= min_grace_epochs
+}
+
 fn get_id(key: &Key) -> Option<u64> {
     match key.get_at(2) {
         Some(id) => match id {
@@ -374,15 +945,9 @@ fn get_id(key: &Key) -> Option<u64> {
     }
 }
 
-fn read<T, DB, H, CA>(
-    context: &Ctx<DB, H, CA>,
-    key: &Key,
-    read_type: ReadType,
-) -> Result<T>
+fn read<T, CTX>(context: &CTX, key: &Key, read_type: ReadType) -> Result<T>
 where
-    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
-    H: 'static + StorageHasher,
-    CA: 'static + WasmCacheAccess,
+    CTX: StorageReader,
     T: Clone + BorshDeserialize,
 {
     let storage_result = match read_type {
@@ -390,13 +955,10 @@ where
         ReadType::POST => context.read_post(key),
     };
 
-    match storage_result {
-        Ok(value) => match value {
-            Some(bytes) => T::try_from_slice(&bytes)
-                .map_err(Error::NativeVpDeserializationError),
-            None => Err(Error::NativeVpNonExistingKeyError(key.to_string())),
-        },
-        Err(err) => Err(Error::NativeVpError(err)),
+    match storage_result? {
+        Some(bytes) => T::try_from_slice(&bytes)
+            .map_err(Error::NativeVpDeserializationError),
+        None => Err(Error::NativeVpNonExistingKeyError(key.to_string())),
     }
 }
 
@@ -405,8 +967,12 @@ enum KeyType {
     VOTE,
     CONTENT,
     #[allow(non_camel_case_types)]
+    PROPOSAL_TYPE,
+    #[allow(non_camel_case_types)]
     PROPOSAL_CODE,
     #[allow(non_camel_case_types)]
+    PROPOSAL_PAYLOAD,
+    #[allow(non_camel_case_types)]
     GRACE_EPOCH,
     #[allow(non_camel_case_types)]
     START_EPOCH,
@@ -416,6 +982,9 @@ enum KeyType {
     BALANCE,
     AUTHOR,
     PARAMETER,
+    PGF,
+    #[allow(non_camel_case_types)]
+    PENDING_GRACE_EPOCH_TRACKER,
     UNKNOWN,
 }
 
@@ -425,8 +994,12 @@ impl From<&Key> for KeyType {
             KeyType::VOTE
         } else if gov_storage::is_content_key(value) {
             KeyType::CONTENT
+        } else if gov_storage::is_proposal_type_key(value) {
+            KeyType::PROPOSAL_TYPE
         } else if gov_storage::is_proposal_code_key(value) {
             KeyType::PROPOSAL_CODE
+        } else if gov_storage::is_proposal_payload_key(value) {
+            KeyType::PROPOSAL_PAYLOAD
         } else if gov_storage::is_grace_epoch_key(value) {
             KeyType::GRACE_EPOCH
         } else if gov_storage::is_start_epoch_key(value) {
@@ -441,6 +1014,10 @@ impl From<&Key> for KeyType {
             KeyType::COUNTER
         } else if gov_storage::is_parameter_key(value) {
             KeyType::PARAMETER
+        } else if gov_storage::is_pgf_key(value) {
+            KeyType::PGF
+        } else if gov_storage::is_last_proposal_grace_epoch_key(value) {
+            KeyType::PENDING_GRACE_EPOCH_TRACKER
         } else if token_storage::is_balance_key(&m1t(), value).is_some() {
             KeyType::BALANCE
         } else {
@@ -453,3 +1030,87 @@ enum ReadType {
     PRE,
     POST,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vote_window_accepts_epochs_inside_the_range() {
+        assert!(is_valid_vote_window(10, 10, 20));
+        assert!(is_valid_vote_window(15, 10, 20));
+        assert!(is_valid_vote_window(20, 10, 20));
+    }
+
+    #[test]
+    fn vote_window_rejects_epochs_outside_the_range() {
+        assert!(!is_valid_vote_window(9, 10, 20));
+        assert!(!is_valid_vote_window(21, 10, 20));
+    }
+
+    #[test]
+    fn get_id_parses_the_proposal_id_segment() {
+        let key = Key::parse("gov/proposal/42/vote").unwrap();
+        assert_eq!(get_id(&key), Some(42));
+    }
+
+    #[test]
+    fn get_id_rejects_a_non_numeric_segment() {
+        let key = Key::parse("gov/proposal/not-a-number/vote").unwrap();
+        assert_eq!(get_id(&key), None);
+    }
+
+    #[test]
+    fn default_proposals_do_not_require_a_payload_key() {
+        assert!(!proposal_type_requires_payload_key(ProposalType::Default));
+    }
+
+    #[test]
+    fn pgf_and_eth_bridge_proposals_require_a_payload_key() {
+        assert!(proposal_type_requires_payload_key(
+            ProposalType::PgfSteward
+        ));
+        assert!(proposal_type_requires_payload_key(
+            ProposalType::PgfFunding
+        ));
+        assert!(proposal_type_requires_payload_key(
+            ProposalType::EthBridge
+        ));
+    }
+
+    #[test]
+    fn congestion_window_allows_a_grace_epoch_that_already_passed() {
+        assert!(is_outside_congestion_window(100, 50, 20));
+    }
+
+    #[test]
+    fn congestion_window_allows_a_sufficiently_far_grace_epoch() {
+        assert!(is_outside_congestion_window(100, 130, 20));
+    }
+
+    #[test]
+    fn congestion_window_rejects_a_nearby_queued_grace_epoch() {
+        assert!(!is_outside_congestion_window(100, 110, 20));
+    }
+
+    #[test]
+    fn eth_bridge_action_requires_both_action_and_signature() {
+        let well_formed = EthBridgeAction {
+            action: vec![1],
+            signature: vec![2],
+        };
+        assert!(well_formed.is_well_formed());
+
+        let missing_action = EthBridgeAction {
+            action: vec![],
+            signature: vec![2],
+        };
+        assert!(!missing_action.is_well_formed());
+
+        let missing_signature = EthBridgeAction {
+            action: vec![1],
+            signature: vec![],
+        };
+        assert!(!missing_signature.is_well_formed());
+    }
+}
@@ -0,0 +1,30 @@
+//! Read-only queries over governance storage.
+
+use borsh::BorshDeserialize;
+
+use super::storage as gov_storage;
+use crate::ledger::storage::{self as ledger_storage, Storage, StorageHasher};
+use crate::types::address::Address;
+use crate::types::storage::DbKeySeg;
+use crate::types::token::Amount;
+
+/// List the addresses currently funded through continuous PGF payouts,
+/// together with their per-epoch stipend.
+pub fn pgf_recipients<D, H>(storage: &Storage<D, H>) -> Vec<(Address, Amount)>
+where
+    D: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let prefix = gov_storage::get_pgf_recipients_prefix_key();
+    let (iter, _gas) = storage.iter_prefix(&prefix);
+    iter.filter_map(|(key, bytes, _gas)| {
+        let key = crate::types::storage::Key::parse(key).ok()?;
+        let recipient = match key.get_at(3) {
+            Some(DbKeySeg::AddressSeg(recipient)) => recipient.clone(),
+            _ => return None,
+        };
+        let stipend = Amount::try_from_slice(&bytes).ok()?;
+        Some((recipient, stipend))
+    })
+    .collect()
+}
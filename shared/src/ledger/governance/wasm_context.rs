@@ -0,0 +1,39 @@
+//! A `StorageReader` implementation backed by the WASM VP host
+//! environment, so `governance::validate_tx` runs unmodified whether
+//! `GovernanceVp` is linked natively or compiled to a WASM VP.
+
+use super::{Error, Result, StorageReader};
+use crate::types::address::Address;
+use crate::types::storage::{Epoch, Key};
+use crate::vm::host_env::vp as vp_host_fns;
+
+/// A `StorageReader` over the imported functions a WASM VP calls into the
+/// host for, rather than over a native `Ctx`.
+#[derive(Debug, Default)]
+pub struct WasmVpContext;
+
+impl StorageReader for WasmVpContext {
+    fn read_pre(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        vp_host_fns::read_pre(key).map_err(Error::from)
+    }
+
+    fn read_post(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        vp_host_fns::read_post(key).map_err(Error::from)
+    }
+
+    fn has_key_pre(&self, key: &Key) -> Result<bool> {
+        vp_host_fns::has_key_pre(key).map_err(Error::from)
+    }
+
+    fn get_block_epoch(&self) -> Result<Epoch> {
+        vp_host_fns::get_block_epoch().map_err(Error::from)
+    }
+
+    fn is_validator(&self, address: &Address, epoch: Epoch) -> Result<bool> {
+        vp_host_fns::is_validator(address, epoch).map_err(Error::from)
+    }
+
+    fn is_delegator(&self, address: &Address, epoch: Epoch) -> Result<bool> {
+        vp_host_fns::is_delegator(address, epoch).map_err(Error::from)
+    }
+}